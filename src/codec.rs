@@ -0,0 +1,80 @@
+//! Compression codec detection and (de)wrapping for FASTQ input/output streams.
+//!
+//! Butcher used to hard-code gzip on both ends of the pipe. This module picks the
+//! right codec from a file's extension so reads can come in as plain text, gzip,
+//! bzip2, xz, or zstd, and be written back out in whichever of those the user asks for.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+
+use bzip2::read::BzDecoder;
+use bzip2::write::BzEncoder;
+use bzip2::Compression as BzCompression;
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression as GzCompression;
+use xz2::read::XzDecoder;
+use xz2::write::XzEncoder;
+use zstd::stream::read::Decoder as ZstdDecoder;
+use zstd::stream::write::Encoder as ZstdEncoder;
+
+/// The compression codecs butcher understands, detected from a file's extension.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Codec {
+    Gzip,
+    Bzip2,
+    Xz,
+    Zstd,
+    None,
+}
+
+impl Codec {
+    /// Pick a codec from a path's extension (`.gz`, `.bz2`, `.xz`, `.zst`), defaulting
+    /// to `None` (plain text) for anything else, including stdin (`-`).
+    pub fn from_path(path: &str) -> Codec {
+        match Path::new(path).extension().and_then(|e| e.to_str()) {
+            Some("gz") => Codec::Gzip,
+            Some("bz2") => Codec::Bzip2,
+            Some("xz") => Codec::Xz,
+            Some("zst") => Codec::Zstd,
+            _ => Codec::None,
+        }
+    }
+
+    /// Wrap a raw reader in the decoder matching this codec.
+    pub fn decoder(&self, inner: Box<dyn io::Read + Send>) -> io::Result<Box<dyn BufRead + Send>> {
+        Ok(match self {
+            Codec::Gzip => Box::new(BufReader::new(MultiGzDecoder::new(inner))),
+            Codec::Bzip2 => Box::new(BufReader::new(BzDecoder::new(inner))),
+            Codec::Xz => Box::new(BufReader::new(XzDecoder::new(inner))),
+            Codec::Zstd => Box::new(BufReader::new(ZstdDecoder::new(inner)?)),
+            Codec::None => Box::new(BufReader::new(inner)),
+        })
+    }
+
+    /// Wrap a raw writer in the encoder matching this codec, at the given compression level.
+    pub fn encoder(&self, writer: Box<dyn Write + Send>, level: u32) -> io::Result<Box<dyn Write + Send>> {
+        Ok(match self {
+            Codec::Gzip => Box::new(GzEncoder::new(writer, GzCompression::new(level))),
+            Codec::Bzip2 => Box::new(BzEncoder::new(writer, BzCompression::new(level))),
+            Codec::Xz => Box::new(XzEncoder::new(writer, level)),
+            Codec::Zstd => Box::new(ZstdEncoder::new(writer, level as i32)?.auto_finish()),
+            Codec::None => writer,
+        })
+    }
+}
+
+/// Open `path` and wrap it in the decoder matching its extension.
+pub fn open_decoded(path: &str) -> io::Result<Box<dyn BufRead + Send>> {
+    let codec = Codec::from_path(path);
+    let file = File::open(path)?;
+    codec.decoder(Box::new(file))
+}
+
+/// Create `path` and wrap it in the encoder matching its extension, at `level`.
+pub fn create_encoded(path: &str, level: u32) -> io::Result<Box<dyn Write + Send>> {
+    let codec = Codec::from_path(path);
+    let file = File::create(path)?;
+    codec.encoder(Box::new(file), level)
+}
@@ -0,0 +1,185 @@
+//! Barcode demultiplexing: route reads to per-sample output files based on an index
+//! read, extracted either from the FASTQ header or from the leading bases of the read
+//! itself.
+
+use std::collections::HashMap;
+use std::io::{self, BufWriter, Write};
+
+use crate::codec::create_encoded;
+use crate::{write_read, FastqInputFile, FastqRecord};
+
+/// Name used for reads whose barcode didn't match any sample within the mismatch budget.
+pub const UNASSIGNED_SAMPLE: &str = "unassigned";
+
+/// A parsed `sample<TAB>barcode` table, used to assign each read to a sample.
+pub struct BarcodeTable {
+    barcode_to_sample: HashMap<Vec<u8>, String>,
+    barcode_length: usize,
+}
+
+impl BarcodeTable {
+    /// Parse a two-column (sample, barcode) file, optionally reverse-complementing
+    /// each barcode before indexing it.
+    pub fn from_file(path: &str, revcomp: &bool) -> Result<BarcodeTable, io::Error> {
+        let contents = std::fs::read_to_string(path)?;
+        let mut barcode_to_sample = HashMap::new();
+        let mut barcode_length = 0;
+
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let mut fields = line.split('\t');
+            let sample = fields.next().expect("missing sample column in barcode file").to_string();
+            let barcode = fields.next().expect("missing barcode column in barcode file").as_bytes().to_vec();
+            let barcode = if *revcomp { reverse_complement(&barcode) } else { barcode };
+
+            barcode_length = barcode_length.max(barcode.len());
+            barcode_to_sample.insert(barcode, sample);
+        }
+
+        Ok(BarcodeTable { barcode_to_sample, barcode_length })
+    }
+
+    /// Extract the candidate index for a read: the trailing field of an Illumina-style
+    /// header (`1:N:0:ACGT...`) if present, otherwise the leading `barcode_length` bases
+    /// of the read itself.
+    pub fn extract_index(&self, read: &FastqRecord) -> Vec<u8> {
+        match header_index(&read.name) {
+            Some(index) => index,
+            None => read.seq.iter().take(self.barcode_length).cloned().collect(),
+        }
+    }
+
+    /// Assign a read's index to a sample name, allowing up to `max_mismatch` mismatches.
+    /// Ties at the minimum distance are sent to [`UNASSIGNED_SAMPLE`].
+    pub fn assign(&self, index: &[u8], max_mismatch: &u8) -> String {
+        let mut best_distance = usize::MAX;
+        let mut best_sample: Option<&str> = None;
+        let mut tied = false;
+
+        for (barcode, sample) in &self.barcode_to_sample {
+            let distance = hamming_distance(barcode, index);
+            if distance < best_distance {
+                best_distance = distance;
+                best_sample = Some(sample);
+                tied = false;
+            } else if distance == best_distance {
+                tied = true;
+            }
+        }
+
+        match best_sample {
+            Some(sample) if best_distance <= *max_mismatch as usize && !tied => sample.to_string(),
+            _ => UNASSIGNED_SAMPLE.to_string(),
+        }
+    }
+}
+
+/// Hamming distance between two byte slices; bases past the shorter slice's length each
+/// count as a mismatch.
+fn hamming_distance(a: &[u8], b: &[u8]) -> usize {
+    let mismatches = a.iter().zip(b.iter()).filter(|(x, y)| x != y).count();
+    mismatches + a.len().abs_diff(b.len())
+}
+
+/// Pull the index off the end of an Illumina header, e.g. `@read 1:N:0:ACGTACGT` -> `ACGTACGT`.
+fn header_index(name: &[u8]) -> Option<Vec<u8>> {
+    let name = String::from_utf8_lossy(name);
+    let field = name.trim_end().rsplit(' ').next()?;
+    let index = field.rsplit(':').next()?;
+    if index.is_empty() || index.bytes().any(|b| !matches!(b, b'A' | b'C' | b'G' | b'T' | b'N')) {
+        None
+    } else {
+        Some(index.as_bytes().to_vec())
+    }
+}
+
+fn reverse_complement(seq: &[u8]) -> Vec<u8> {
+    seq.iter().rev().map(|base| match base {
+        b'A' => b'T', b'T' => b'A', b'C' => b'G', b'G' => b'C',
+        b'a' => b't', b't' => b'a', b'c' => b'g', b'g' => b'c',
+        other => *other,
+    }).collect()
+}
+
+/// Per-sample output files, opened lazily the first time a sample is written to. For
+/// paired-end input, read1/read2 for the same sample share a pair of writers.
+pub struct DemuxWriters {
+    out_dir: String,
+    compression_level: u32,
+    paired: bool,
+    writers: HashMap<String, (BufWriter<Box<dyn Write + Send>>, Option<BufWriter<Box<dyn Write + Send>>>)>,
+}
+
+impl DemuxWriters {
+    pub fn new(out_dir: &str, paired: &bool, compression_level: &u32) -> DemuxWriters {
+        DemuxWriters {
+            out_dir: out_dir.to_string(),
+            compression_level: *compression_level,
+            paired: *paired,
+            writers: HashMap::new(),
+        }
+    }
+
+    fn open_for(&mut self, sample: &str) -> io::Result<&mut (BufWriter<Box<dyn Write + Send>>, Option<BufWriter<Box<dyn Write + Send>>>)> {
+        if !self.writers.contains_key(sample) {
+            let path1 = format!("{}/{}.R1.fastq.gz", self.out_dir, sample);
+            let writer1 = BufWriter::new(create_encoded(&path1, self.compression_level)?);
+            let writer2 = if self.paired {
+                let path2 = format!("{}/{}.R2.fastq.gz", self.out_dir, sample);
+                Some(BufWriter::new(create_encoded(&path2, self.compression_level)?))
+            } else {
+                None
+            };
+            self.writers.insert(sample.to_string(), (writer1, writer2));
+        }
+        Ok(self.writers.get_mut(sample).unwrap())
+    }
+
+    /// Write a single-end read to its sample's output file, opening it on first use.
+    pub fn write_single(&mut self, sample: &str, read: &FastqRecord) -> io::Result<()> {
+        let (writer1, _) = self.open_for(sample)?;
+        write_read(writer1, read)
+    }
+
+    /// Write a paired-end read pair to its sample's output file pair, opening them on first use.
+    pub fn write_paired(&mut self, sample: &str, read1: &FastqRecord, read2: &FastqRecord) -> io::Result<()> {
+        let (writer1, writer2) = self.open_for(sample)?;
+        write_read(writer1, read1)?;
+        write_read(writer2.as_mut().expect("paired demux writer missing a read2 output"), read2)
+    }
+
+    pub fn flush_all(&mut self) -> io::Result<()> {
+        for (writer1, writer2) in self.writers.values_mut() {
+            writer1.flush()?;
+            if let Some(writer2) = writer2 {
+                writer2.flush()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Single-end demux loop: assign each read to a sample and write it to that sample's file.
+pub fn demux_single_end(reader: &mut FastqInputFile, table: &BarcodeTable, writers: &mut DemuxWriters, max_mismatch: &u8) {
+    while let Some(read) = reader.read_next() {
+        let index = table.extract_index(&read);
+        let sample = table.assign(&index, max_mismatch);
+        writers.write_single(&sample, &read).expect("unable to write demultiplexed read");
+    }
+    writers.flush_all().expect("unable to flush demultiplexed output files");
+}
+
+/// Paired-end demux loop: assign each read pair to a sample (using read1's index) and
+/// write both mates to that sample's file pair.
+pub fn demux_paired_end(reader1: &mut FastqInputFile, reader2: &mut FastqInputFile, table: &BarcodeTable, writers: &mut DemuxWriters, max_mismatch: &u8) {
+    while let Some(read1) = reader1.read_next() {
+        let read2 = reader2.read_next().expect("reads in fastq1 and fastq2 are not paired");
+        let index = table.extract_index(&read1);
+        let sample = table.assign(&index, max_mismatch);
+        writers.write_paired(&sample, &read1, &read2).expect("unable to write demultiplexed read pair");
+    }
+    writers.flush_all().expect("unable to flush demultiplexed output files");
+}
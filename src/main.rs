@@ -3,25 +3,34 @@
 extern crate colored;
 extern crate clap;
 extern crate flate2;
+extern crate bzip2;
+extern crate xz2;
+extern crate zstd;
+extern crate serde;
+extern crate serde_json;
 extern crate core;
 
 mod trimmers;
 mod primers;
+mod codec;
+mod demux;
+mod pipeline;
+mod report;
 
 use std::io;
-use flate2::read::MultiGzDecoder;
-use std::fs::File;
-use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::io::{BufRead, BufReader, BufWriter, IsTerminal, Write};
 use clap::Parser;
-use flate2::Compression;
-use flate2::write::GzEncoder;
+use crate::codec::{create_encoded, open_decoded};
+use crate::demux::{demux_paired_end, demux_single_end, BarcodeTable, DemuxWriters};
+use crate::pipeline::{paired_end_threaded, single_end_threaded};
+use crate::report::Report;
 use crate::trimmers::{BackTrimmer, FastqTrimmer, FrontBackTrimmer, PolyXTrimmer, PrimerTrimmer, TrimResult};
 use log::{debug, info, warn};
 
 /// A constrained use-case fastq trimmer
 #[derive(Parser, Debug)]
 struct Args {
-    /// the first fastq file -- required
+    /// the first fastq file -- pass '-' or omit it entirely to read from stdin
     #[arg(long)]
     fastq1: Option<String>,
 
@@ -37,6 +46,10 @@ struct Args {
     #[arg(long)]
     out_fastq2: Option<String>,
 
+    /// compression level to use when writing a compressed output file (1-9, codec-dependent; output codec is picked from the out_fastq file extension)
+    #[arg(long, default_value_t = 6)]
+    compression_level: u32,
+
     /// minimum remaining read size after trimming is complete -- reads shorter than this will be discarded
     #[arg(long, default_value_t = 10)]
     minimum_remaining_read_size: usize,
@@ -84,6 +97,33 @@ struct Args {
     /// just display the reads and what we'd cut, don't actually write any output to disk
     #[arg(long, default_value_t = false)]
     preview: bool,
+
+    /// a two-column (sample, barcode) TAB-separated file -- when set, butcher demultiplexes
+    /// reads into per-sample files instead of trimming
+    #[arg(long)]
+    demux_barcodes: Option<String>,
+
+    /// directory to write demultiplexed per-sample fastq files into
+    #[arg(long, default_value = ".")]
+    demux_out_dir: String,
+
+    /// reverse-complement each barcode in the demux barcode file before matching
+    #[arg(long, default_value_t = false)]
+    demux_revcomp: bool,
+
+    /// the maximum Hamming distance allowed between a read's index and a sample barcode
+    #[arg(long, default_value_t = 1)]
+    demux_max_mismatch: u8,
+
+    /// number of worker threads to use for trimming -- 1 runs the simple serial loop,
+    /// more spreads the trimmer chain across a reader/worker-pool/writer pipeline
+    #[arg(long, default_value_t = 1)]
+    threads: usize,
+
+    /// write a JSON summary of what trimming did (reads kept/dropped, bases trimmed per
+    /// trimmer) to this file
+    #[arg(long)]
+    report: Option<String>,
 }
 
 /// a simple FASTQ record with name, sequence, and quality
@@ -99,68 +139,128 @@ impl FastqRecord {
     }
 }
 
-/// an input decoder for our gzipped FASTQ file
-struct FastqInputFile {
-    decoder: BufReader<MultiGzDecoder<File>>,
+/// a FASTQ record failed to parse -- the file is malformed or truncated at this point
+#[derive(Debug)]
+pub enum ParseError {
+    Io(io::Error),
+    /// the name line didn't start with `@`
+    MissingNameMarker(Vec<u8>),
+    /// the separator line for this read didn't start with `+`
+    MissingPlusMarker(Vec<u8>),
+    /// the sequence and quality lines for this read have different lengths
+    LengthMismatch { name: Vec<u8>, seq_len: usize, qual_len: usize },
+    /// the file ended partway through a record
+    TruncatedRecord,
 }
 
-impl FastqInputFile {
-    pub fn new(path: &str) -> Result<FastqInputFile, io::Error> {
-        let file = File::open(path)?;
-        let decoder = io::BufReader::new(MultiGzDecoder::new(file));
-        Ok(FastqInputFile { decoder })
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ParseError::Io(e) => write!(f, "I/O error while reading a FASTQ record: {}", e),
+            ParseError::MissingNameMarker(line) => write!(f, "expected a '@' name line, got: {}", String::from_utf8_lossy(line)),
+            ParseError::MissingPlusMarker(name) => write!(f, "expected a '+' separator line for read {}", String::from_utf8_lossy(name)),
+            ParseError::LengthMismatch { name, seq_len, qual_len } => write!(f, "read {} has {} bases but {} quality scores", String::from_utf8_lossy(name), seq_len, qual_len),
+            ParseError::TruncatedRecord => write!(f, "file ended partway through a record"),
+        }
     }
 }
 
-impl Iterator for FastqInputFile {
-    type Item = FastqRecord;
+impl std::error::Error for ParseError {}
+
+/// an input decoder for our FASTQ file, auto-detecting compression from the file extension
+pub(crate) struct FastqInputFile {
+    decoder: Box<dyn BufRead + Send>,
+    /// how many records this file failed to parse before iteration stopped
+    pub(crate) error_count: usize,
+}
 
-    fn next(&mut self) -> Option<FastqRecord> {
-        let mut name = String::new();
-        match self.decoder.read_line(&mut name) {
-            Ok(_) => {
+impl FastqInputFile {
+    /// Open `path` for reading, auto-detecting compression from its extension. Pass `-`
+    /// to read plain-text FASTQ from stdin instead of a file.
+    pub fn new(path: &str) -> Result<FastqInputFile, io::Error> {
+        let decoder: Box<dyn BufRead + Send> = if path == "-" {
+            Box::new(BufReader::new(io::stdin()))
+        } else {
+            open_decoded(path)?
+        };
+        Ok(FastqInputFile { decoder, error_count: 0 })
+    }
+
+    /// Read the next record, warning and stopping iteration on any parse error -- once
+    /// the four-line framing is off we can no longer trust where the next record starts.
+    pub fn read_next(&mut self) -> Option<FastqRecord> {
+        match Iterator::next(self) {
+            Some(Ok(record)) => Some(record),
+            Some(Err(e)) => {
+                self.error_count += 1;
+                warn!("Stopping read of input file after a parse error: {}", e);
+                None
             }
-            Err(_e) => {
-                warn!("Error reading sequence line for unnamed read");
-                return None
-            },
+            None => None,
         }
-        let mut name = name.into_bytes();
-        if name.len() == 0 {
-            return None;
+    }
 
+    /// Read one line, stripping a trailing `\n` or `\r\n`. Returns `Ok(None)` on a clean EOF.
+    fn read_line(&mut self) -> Result<Option<Vec<u8>>, ParseError> {
+        let mut line = String::new();
+        match self.decoder.read_line(&mut line) {
+            Ok(0) => Ok(None),
+            Ok(_) => Ok(Some(strip_newline(line.into_bytes()))),
+            Err(e) => Err(ParseError::Io(e)),
         }
-        assert_eq!(name[0], b'@');
-        name.pop(); // drop endline
-
-        let mut seq = String::new();
-        match self.decoder.read_line(&mut seq) {
-            Ok(_) => {}
-            Err(_e) => {
-                warn!("Error reading sequence line for read {}", String::from_utf8(name).unwrap());
-                return None
-            },
+    }
+}
+
+/// Strip a trailing `\n` or `\r\n` from a line read with `BufRead::read_line`.
+fn strip_newline(mut line: Vec<u8>) -> Vec<u8> {
+    if line.last() == Some(&b'\n') {
+        line.pop();
+    }
+    if line.last() == Some(&b'\r') {
+        line.pop();
+    }
+    line
+}
+
+impl Iterator for FastqInputFile {
+    type Item = Result<FastqRecord, ParseError>;
+
+    fn next(&mut self) -> Option<Result<FastqRecord, ParseError>> {
+        let name = match self.read_line() {
+            Ok(None) => return None,
+            Ok(Some(name)) => name,
+            Err(e) => return Some(Err(e)),
+        };
+        if name.first() != Some(&b'@') {
+            return Some(Err(ParseError::MissingNameMarker(name)));
         }
-        seq.pop(); // drop endline
-        let mut _orient = String::new();
-        match self.decoder.read_line(&mut _orient) {
-            Ok(_) => {}
-            Err(_e) => {
-                warn!("Error reading orientation line for read {}", String::from_utf8(name).unwrap());
-                return None
-            },
+
+        let seq = match self.read_line() {
+            Ok(None) => return Some(Err(ParseError::TruncatedRecord)),
+            Ok(Some(seq)) => seq,
+            Err(e) => return Some(Err(e)),
+        };
+
+        let plus = match self.read_line() {
+            Ok(None) => return Some(Err(ParseError::TruncatedRecord)),
+            Ok(Some(plus)) => plus,
+            Err(e) => return Some(Err(e)),
+        };
+        if plus.first() != Some(&b'+') {
+            return Some(Err(ParseError::MissingPlusMarker(name)));
         }
-        let mut quals = String::new();
-        match self.decoder.read_line(&mut quals) {
-            Ok(_) => {}
-            Err(_e) => {
-                warn!("Error reading quals line for read {}", String::from_utf8(name).unwrap());
-                return None
-            },
+
+        let quals = match self.read_line() {
+            Ok(None) => return Some(Err(ParseError::TruncatedRecord)),
+            Ok(Some(quals)) => quals,
+            Err(e) => return Some(Err(e)),
+        };
+
+        if seq.len() != quals.len() {
+            return Some(Err(ParseError::LengthMismatch { name, seq_len: seq.len(), qual_len: quals.len() }));
         }
-        quals.pop(); // drop endline
 
-        Some(FastqRecord { name, seq: seq.into_bytes(), quals: quals.into_bytes() })
+        Some(Ok(FastqRecord { name, seq, quals }))
     }
 }
 
@@ -169,15 +269,37 @@ fn main() {
     simple_logger::init_with_level(log::Level::Warn).unwrap();
 
     let args = Args::parse();
+    let fastq1 = resolve_fastq1(&args.fastq1);
+
+    if let Some(barcodes) = &args.demux_barcodes {
+        let table = BarcodeTable::from_file(barcodes, &args.demux_revcomp).expect("invalid demux barcode file");
+        let mut writers = DemuxWriters::new(&args.demux_out_dir, &args.fastq2.is_some(), &args.compression_level);
+        let mut reader = FastqInputFile::new(&fastq1).expect("invalid path/file for fastq1");
+
+        match &args.fastq2 {
+            Some(fastq2) => {
+                let mut reader2 = FastqInputFile::new(fastq2).expect("invalid path/file for fastq2");
+                demux_paired_end(&mut reader, &mut reader2, &table, &mut writers, &args.demux_max_mismatch);
+                warn_on_parse_errors("fastq1", &reader);
+                warn_on_parse_errors("fastq2", &reader2);
+            }
+            None => {
+                demux_single_end(&mut reader, &table, &mut writers, &args.demux_max_mismatch);
+                warn_on_parse_errors("fastq1", &reader);
+            }
+        }
+        return;
+    }
 
     assert!(args.preview ^ args.out_fastq1.is_some(), "Either preview mode or output files need to be set");
 
-    let mut out_fastq1 = setup_compressed_file(&args.out_fastq1);
-    let mut out_fastq2 = setup_compressed_file(&args.out_fastq2);
+    let mut out_fastq1 = setup_compressed_file(&args.out_fastq1, args.compression_level);
+    let mut out_fastq2 = setup_compressed_file(&args.out_fastq2, args.compression_level);
 
-    let mut reader = FastqInputFile::new(&args.fastq1.unwrap()).expect("invalid path/file for fastq1");
+    let mut reader = FastqInputFile::new(&fastq1).expect("invalid path/file for fastq1");
 
-    let mut cutters: Vec<Box<dyn FastqTrimmer>> = Vec::new();
+    let mut cutters: Vec<Box<dyn FastqTrimmer + Send + Sync>> = Vec::new();
+    let mut cutter_names: Vec<String> = Vec::new();
 
     if args.primers.is_some() {
         let primers = args.primers.unwrap();
@@ -188,6 +310,7 @@ fn main() {
                                                  &args.primers_max_mismatch_distance,
                                                  &args.primers_end_proportion,
                                                  &args.split_on_internal_primers)));
+        cutter_names.push("primer".to_string());
     }
 
     if args.trim_poly_a {
@@ -196,6 +319,7 @@ fn main() {
             minimum_g_proportion: args.trim_poly_x_proportion.clone(),
             bases: vec![b'A', b'a'],
         }));
+        cutter_names.push("poly_a".to_string());
     }
     if args.trim_poly_g {
         cutters.push(Box::new(PolyXTrimmer {
@@ -203,36 +327,86 @@ fn main() {
             minimum_g_proportion: args.trim_poly_x_proportion.clone(),
             bases: vec![b'G', b'g'],
         }));
+        cutter_names.push("poly_g".to_string());
     }
 
+    if args.report.is_some() && args.threads > 1 {
+        eprintln!("error: --report doesn't yet track per-trimmer stats across worker threads.");
+        eprintln!("usage: run with --threads 1, or drop --report, to pick one.");
+        std::process::exit(1);
+    }
+    if args.preview && args.threads > 1 {
+        eprintln!("error: --preview doesn't support the multi-threaded pipeline (it prints reads as it cuts them).");
+        eprintln!("usage: run with --threads 1, or drop --preview, to pick one.");
+        std::process::exit(1);
+    }
+    let mut report = args.report.as_ref().map(|_| Report::new());
+    let use_threads = args.threads > 1 && !args.preview && report.is_none();
+
     if args.fastq2.is_some() {
         cutters.push(Box::new(BackTrimmer { window_size: args.window_size.clone(), window_min_qual_score: args.window_min_qual_score, qual_score_base: 32 }));
+        cutter_names.push("quality_window_back".to_string());
         let mut reader2 = FastqInputFile::new(&args.fastq2.unwrap()).expect("invalid path/file for fastq2");
-        paired_end(&mut reader, &mut reader2, &mut out_fastq1, &mut out_fastq2, &cutters, &args.minimum_remaining_read_size, &args.preview);
+        if use_threads {
+            paired_end_threaded(&mut reader, &mut reader2, &mut out_fastq1, &mut out_fastq2, &cutters, &args.minimum_remaining_read_size, args.threads);
+        } else {
+            paired_end(&mut reader, &mut reader2, &mut out_fastq1, &mut out_fastq2, &cutters, &cutter_names, &args.minimum_remaining_read_size, &args.preview, &mut report);
+        }
+        warn_on_parse_errors("fastq1", &reader);
+        warn_on_parse_errors("fastq2", &reader2);
     } else {
         cutters.push(Box::new(FrontBackTrimmer { window_size: args.window_size.clone(), window_min_qual_score: args.window_min_qual_score, qual_score_base: 32 }));
-        single_end(&mut reader, &mut out_fastq1, &cutters, &args.minimum_remaining_read_size, &args.preview);
+        cutter_names.push("quality_window_front_back".to_string());
+        if use_threads {
+            single_end_threaded(&mut reader, &mut out_fastq1, &cutters, &args.minimum_remaining_read_size, args.threads);
+        } else {
+            single_end(&mut reader, &mut out_fastq1, &cutters, &cutter_names, &args.minimum_remaining_read_size, &args.preview, &mut report);
+        }
+        warn_on_parse_errors("fastq1", &reader);
+    }
+
+    if let (Some(report), Some(path)) = (&report, &args.report) {
+        report.write_to(path).expect("unable to write trimming report");
     }
 }
 
 fn single_end(reader1: &mut FastqInputFile,
-              out_fastq: &mut BufWriter<GzEncoder<Box<dyn Write>>>,
-              cutters: &Vec<Box<dyn FastqTrimmer>>,
+              out_fastq: &mut BufWriter<Box<dyn Write + Send>>,
+              cutters: &Vec<Box<dyn FastqTrimmer + Send + Sync>>,
+              cutter_names: &Vec<String>,
               minimum_remaining_read_size: &usize,
-              preview: &bool) {
+              preview: &bool,
+              report: &mut Option<Report>) {
+
+    while let Some(read1) = reader1.read_next() {
+        if let Some(report) = report.as_mut() {
+            report.record_read_in();
+        }
 
-    while let Some(read1) = reader1.next() {
         let mut base_cuts = TrimResult::from_read(&read1);
-        for cutter in cutters {
+        for (cutter, name) in cutters.iter().zip(cutter_names) {
             let cut = cutter.trim(&read1);
             debug!("cut: {:?}", cut);
+            if let Some(report) = report.as_mut() {
+                let segments = cut.trim_results_to_reads(&read1);
+                let remaining: usize = segments.iter().map(|r| r.seq.len()).sum();
+                report.record_bases_trimmed(name, read1.seq.len().saturating_sub(remaining));
+            }
             base_cuts = TrimResult::join(vec![base_cuts, cut], &true);
             debug!("base_cuts: {:?}", base_cuts);
         }
 
         debug!("base cuts: {:?}", base_cuts);
         if base_cuts.keep() {
-            for resulting_read in base_cuts.trim_results_to_reads(&read1) {
+            let resulting_reads = base_cuts.trim_results_to_reads(&read1);
+            let kept = resulting_reads.iter().any(|r| r.seq.len() >= *minimum_remaining_read_size);
+            if let Some(report) = report.as_mut() {
+                if resulting_reads.len() > 1 {
+                    report.record_split();
+                }
+                report.record_outcome(kept);
+            }
+            for resulting_read in resulting_reads {
                 if resulting_read.seq.len() >= *minimum_remaining_read_size {
                     match *preview {
                         true => {
@@ -244,6 +418,8 @@ fn single_end(reader1: &mut FastqInputFile,
                     }
                 }
             }
+        } else if let Some(report) = report.as_mut() {
+            report.record_rejected_by_trimmer();
         }
     }
     out_fastq.flush().expect("Unable to flush output fastq file.");
@@ -251,29 +427,46 @@ fn single_end(reader1: &mut FastqInputFile,
 
 fn paired_end(reader1: &mut FastqInputFile,
               reader2: &mut FastqInputFile,
-              out_fastq1: &mut BufWriter<GzEncoder<Box<dyn Write>>>,
-              out_fastq2: &mut BufWriter<GzEncoder<Box<dyn Write>>>,
-              cutters: &Vec<Box<dyn FastqTrimmer>>,
+              out_fastq1: &mut BufWriter<Box<dyn Write + Send>>,
+              out_fastq2: &mut BufWriter<Box<dyn Write + Send>>,
+              cutters: &Vec<Box<dyn FastqTrimmer + Send + Sync>>,
+              cutter_names: &Vec<String>,
               minimum_remaining_read_size: &usize,
-              preview: &bool) {
+              preview: &bool,
+              report: &mut Option<Report>) {
 
-    while let Some(read1) = reader1.next() {
-        let read2 = match reader2.next() {
+    while let Some(read1) = reader1.read_next() {
+        let read2 = match reader2.read_next() {
             None => {panic!("Reads in fastq1 and fastq2 are not paired, at read1 {}",String::from_utf8(read1.name).unwrap())}
             Some(x) => {x}
         };
+        if let Some(report) = report.as_mut() {
+            report.record_read_in();
+        }
 
         let mut base_cuts_read1 = TrimResult::from_read(&read1);
         let mut base_cuts_read2 = TrimResult::from_read(&read2);
 
-        for cutter in cutters {
+        for (cutter, name) in cutters.iter().zip(cutter_names) {
             let cut = cutter.trim(&read1);
+            if let Some(report) = report.as_mut() {
+                let segments = cut.trim_results_to_reads(&read1);
+                let remaining: usize = segments.iter().map(|r| r.seq.len()).sum();
+                report.record_bases_trimmed(name, read1.seq.len().saturating_sub(remaining));
+            }
             base_cuts_read1 = TrimResult::join(vec![base_cuts_read1, cut], &true);
 
             let cut = cutter.trim(&read2);
+            if let Some(report) = report.as_mut() {
+                let segments = cut.trim_results_to_reads(&read2);
+                let remaining: usize = segments.iter().map(|r| r.seq.len()).sum();
+                report.record_bases_trimmed(name, read2.seq.len().saturating_sub(remaining));
+            }
             base_cuts_read2 = TrimResult::join(vec![base_cuts_read2, cut], &true);
         }
 
+        let rejected_by_trimmer = !base_cuts_read1.keep() || !base_cuts_read2.keep();
+
         let resulting_reads1 = base_cuts_read1.trim_results_to_reads(&read1);
         let resulting_reads2 = base_cuts_read2.trim_results_to_reads(&read2);
         assert_eq!(resulting_reads1.len(), resulting_reads2.len(),"{}", format!("Resulting read split from read1: {} and read2: {} are not the same segment lengths ({} and {})",
@@ -281,11 +474,29 @@ fn paired_end(reader1: &mut FastqInputFile,
                                                                           String::from_utf8(read2.name).unwrap(),
                                                                           resulting_reads1.len(),resulting_reads2.len()));
 
+        if let Some(report) = report.as_mut() {
+            if rejected_by_trimmer {
+                report.record_rejected_by_trimmer();
+            } else {
+                // One pair that gets split counts as one split event, no matter how many
+                // cutters contributed or whether one mate or both ended up segmented.
+                if resulting_reads1.len() > 1 {
+                    report.record_split();
+                }
+                let any_segment_kept = (0..resulting_reads1.len()).any(|read_index| {
+                    resulting_reads1[read_index].seq.len() >= *minimum_remaining_read_size
+                        && resulting_reads2[read_index].seq.len() >= *minimum_remaining_read_size
+                });
+                report.record_outcome(any_segment_kept);
+            }
+        }
+
         for read_index in 0..resulting_reads1.len() {
             let read1 = &resulting_reads1[read_index];
             let read2 = &resulting_reads2[read_index];
 
-            if read1.seq.len() >= *minimum_remaining_read_size && read2.seq.len() >= *minimum_remaining_read_size {
+            let kept = read1.seq.len() >= *minimum_remaining_read_size && read2.seq.len() >= *minimum_remaining_read_size;
+            if kept {
                 match *preview {
                     true => {
                         print_read(read1, &base_cuts_read1);
@@ -303,17 +514,41 @@ fn paired_end(reader1: &mut FastqInputFile,
     out_fastq2.flush().expect("Unable to flush output file 2.");
 }
 
-fn setup_compressed_file(fastq_output: &Option<String>) -> BufWriter<GzEncoder<Box<dyn Write>>> {
-    let writer1: Box<dyn Write> = match fastq_output.clone() {
-        Some(file) => Box::new(File::create(file).unwrap()),
+/// Warn at the end of a run if `reader` stopped early because of a parse error, so a
+/// truncated or corrupt input doesn't pass silently just because `read_next` already
+/// logged the individual error.
+fn warn_on_parse_errors(label: &str, reader: &FastqInputFile) {
+    if reader.error_count > 0 {
+        warn!("{} stopped early after {} parse error(s); output may be truncated", label, reader.error_count);
+    }
+}
+
+/// Figure out the path to read fastq1 from: the path the user gave, or stdin (`-`) if
+/// they passed `-` or omitted `--fastq1` entirely. If nothing was given and stdin isn't
+/// piped either, print a usage message and exit instead of panicking deep in the parser.
+fn resolve_fastq1(fastq1: &Option<String>) -> String {
+    match fastq1 {
+        Some(path) => path.clone(),
+        None if !io::stdin().is_terminal() => "-".to_string(),
+        None => {
+            eprintln!("error: no input file specified and no input piped via stdin.");
+            eprintln!("usage: butcher --fastq1 <path> [...]  (or '-'/omit it to read from stdin)");
+            eprintln!("       zcat reads.fq.gz | butcher --fastq1 - [...]");
+            std::process::exit(1);
+        }
+    }
+}
+
+fn setup_compressed_file(fastq_output: &Option<String>, compression_level: u32) -> BufWriter<Box<dyn Write + Send>> {
+    let writer1: Box<dyn Write + Send> = match fastq_output.clone() {
+        Some(file) => create_encoded(&file, compression_level).expect("unable to open output file for writing"),
         None => Box::new(io::stdout()),
     };
 
-    let out_fastq1 = BufWriter::new(GzEncoder::new(writer1, Compression::default()));
-    out_fastq1
+    BufWriter::new(writer1)
 }
 
-pub fn write_read(writer: &mut BufWriter<dyn Write>, record: &FastqRecord) -> Result<(), io::Error> {
+pub fn write_read(writer: &mut BufWriter<Box<dyn Write + Send>>, record: &FastqRecord) -> Result<(), io::Error> {
     writer.write_all(&record.name)?;
     writer.write_all(b"\n")?;
     writer.write_all(&record.seq)?;
@@ -0,0 +1,198 @@
+//! Multi-threaded record processing: a reader thread batches records into chunks, a pool
+//! of worker threads runs the trimmer chain on each chunk, and a writer thread reassembles
+//! the chunks in their original order before writing -- so spreading the trimming work
+//! across cores doesn't reorder (or, for paired-end, desync) the output.
+
+use std::collections::HashMap;
+use std::io::{BufWriter, Write};
+use std::sync::mpsc::sync_channel;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::trimmers::{FastqTrimmer, TrimResult};
+use crate::{write_read, FastqInputFile, FastqRecord};
+
+/// How many records a reader thread batches together before handing a chunk to a worker.
+const CHUNK_SIZE: usize = 1000;
+
+/// Run every cutter over a single read and return the segments it splits into, or an
+/// empty vec if the joined trim result drops the read entirely.
+fn cut_one(read: &FastqRecord, cutters: &[Box<dyn FastqTrimmer + Send + Sync>]) -> Vec<FastqRecord> {
+    let mut base_cuts = TrimResult::from_read(read);
+    for cutter in cutters {
+        let cut = cutter.trim(read);
+        base_cuts = TrimResult::join(vec![base_cuts, cut], &true);
+    }
+
+    if base_cuts.keep() {
+        base_cuts.trim_results_to_reads(read)
+    } else {
+        Vec::new()
+    }
+}
+
+/// Cut a single read and drop any resulting segment shorter than the minimum remaining
+/// read size.
+fn trim_one(
+    read: &FastqRecord,
+    cutters: &[Box<dyn FastqTrimmer + Send + Sync>],
+    minimum_remaining_read_size: &usize,
+) -> Vec<FastqRecord> {
+    cut_one(read, cutters)
+        .into_iter()
+        .filter(|read| read.seq.len() >= *minimum_remaining_read_size)
+        .collect()
+}
+
+/// Single-end trimming across `threads` worker threads, writing output in original order.
+pub fn single_end_threaded(
+    reader: &mut FastqInputFile,
+    out_fastq: &mut BufWriter<Box<dyn Write + Send>>,
+    cutters: &Vec<Box<dyn FastqTrimmer + Send + Sync>>,
+    minimum_remaining_read_size: &usize,
+    threads: usize,
+) {
+    thread::scope(|scope| {
+        let (chunk_tx, chunk_rx) = sync_channel::<(usize, Vec<FastqRecord>)>(threads * 2);
+        let chunk_rx = Arc::new(Mutex::new(chunk_rx));
+        let (result_tx, result_rx) = sync_channel::<(usize, Vec<FastqRecord>)>(threads * 2);
+
+        scope.spawn(move || {
+            let mut index = 0usize;
+            loop {
+                let mut chunk = Vec::with_capacity(CHUNK_SIZE);
+                while chunk.len() < CHUNK_SIZE {
+                    match reader.read_next() {
+                        Some(read) => chunk.push(read),
+                        None => break,
+                    }
+                }
+                let reached_eof = chunk.len() < CHUNK_SIZE;
+                if !chunk.is_empty() && chunk_tx.send((index, chunk)).is_err() {
+                    break;
+                }
+                index += 1;
+                if reached_eof {
+                    break;
+                }
+            }
+        });
+
+        for _ in 0..threads {
+            let chunk_rx = Arc::clone(&chunk_rx);
+            let result_tx = result_tx.clone();
+            scope.spawn(move || loop {
+                let next = chunk_rx.lock().expect("chunk queue lock poisoned").recv();
+                let (index, chunk) = match next {
+                    Ok(item) => item,
+                    Err(_) => break,
+                };
+                let processed = chunk
+                    .iter()
+                    .flat_map(|read| trim_one(read, cutters, minimum_remaining_read_size))
+                    .collect();
+                if result_tx.send((index, processed)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(result_tx);
+
+        let mut next_index = 0usize;
+        let mut pending: HashMap<usize, Vec<FastqRecord>> = HashMap::new();
+        for (index, processed) in result_rx {
+            pending.insert(index, processed);
+            while let Some(chunk) = pending.remove(&next_index) {
+                for read in &chunk {
+                    write_read(out_fastq, read).expect("unable to write to output file 1.");
+                }
+                next_index += 1;
+            }
+        }
+    });
+    out_fastq.flush().expect("unable to flush output fastq file.");
+}
+
+/// Paired-end trimming across `threads` worker threads, writing output in original order
+/// with read1/read2 kept in sync.
+pub fn paired_end_threaded(
+    reader1: &mut FastqInputFile,
+    reader2: &mut FastqInputFile,
+    out_fastq1: &mut BufWriter<Box<dyn Write + Send>>,
+    out_fastq2: &mut BufWriter<Box<dyn Write + Send>>,
+    cutters: &Vec<Box<dyn FastqTrimmer + Send + Sync>>,
+    minimum_remaining_read_size: &usize,
+    threads: usize,
+) {
+    thread::scope(|scope| {
+        let (chunk_tx, chunk_rx) = sync_channel::<(usize, Vec<(FastqRecord, FastqRecord)>)>(threads * 2);
+        let chunk_rx = Arc::new(Mutex::new(chunk_rx));
+        let (result_tx, result_rx) = sync_channel::<(usize, Vec<(FastqRecord, FastqRecord)>)>(threads * 2);
+
+        scope.spawn(move || {
+            let mut index = 0usize;
+            loop {
+                let mut chunk = Vec::with_capacity(CHUNK_SIZE);
+                while chunk.len() < CHUNK_SIZE {
+                    match (reader1.read_next(), reader2.read_next()) {
+                        (Some(read1), Some(read2)) => chunk.push((read1, read2)),
+                        (Some(read1), None) => {
+                            panic!("Reads in fastq1 and fastq2 are not paired, at read1 {}", String::from_utf8(read1.name).unwrap())
+                        }
+                        (None, _) => break,
+                    }
+                }
+                let reached_eof = chunk.len() < CHUNK_SIZE;
+                if !chunk.is_empty() && chunk_tx.send((index, chunk)).is_err() {
+                    break;
+                }
+                index += 1;
+                if reached_eof {
+                    break;
+                }
+            }
+        });
+
+        for _ in 0..threads {
+            let chunk_rx = Arc::clone(&chunk_rx);
+            let result_tx = result_tx.clone();
+            scope.spawn(move || loop {
+                let next = chunk_rx.lock().expect("chunk queue lock poisoned").recv();
+                let (index, chunk) = match next {
+                    Ok(item) => item,
+                    Err(_) => break,
+                };
+                let processed = chunk
+                    .iter()
+                    .flat_map(|(read1, read2)| {
+                        let segments1 = cut_one(read1, cutters);
+                        let segments2 = cut_one(read2, cutters);
+                        assert_eq!(segments1.len(), segments2.len(), "paired reads split into a different number of segments");
+                        segments1.into_iter().zip(segments2).filter(|(read1, read2)| {
+                            read1.seq.len() >= *minimum_remaining_read_size && read2.seq.len() >= *minimum_remaining_read_size
+                        })
+                    })
+                    .collect();
+                if result_tx.send((index, processed)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(result_tx);
+
+        let mut next_index = 0usize;
+        let mut pending: HashMap<usize, Vec<(FastqRecord, FastqRecord)>> = HashMap::new();
+        for (index, processed) in result_rx {
+            pending.insert(index, processed);
+            while let Some(chunk) = pending.remove(&next_index) {
+                for (read1, read2) in &chunk {
+                    write_read(out_fastq1, read1).expect("unable to write to output file 1.");
+                    write_read(out_fastq2, read2).expect("unable to write to output file 2.");
+                }
+                next_index += 1;
+            }
+        }
+    });
+    out_fastq1.flush().expect("unable to flush output file 1.");
+    out_fastq2.flush().expect("unable to flush output file 2.");
+}
@@ -0,0 +1,68 @@
+//! A JSON trimming report: how many reads came in, how many were kept or dropped (and
+//! why), and how many bases each named trimmer removed.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+
+use serde::Serialize;
+
+/// Per-trimmer and overall counters accumulated while trimming a single run.
+#[derive(Default, Serialize)]
+pub struct Report {
+    pub reads_in: u64,
+    pub reads_kept: u64,
+    /// Reads (or read pairs) that a trimmer rejected outright, e.g. an internal primer
+    /// hit with `--split-on-internal-primers` off.
+    pub reads_rejected_by_trimmer: u64,
+    /// Reads (or read pairs) that survived trimming but came out shorter than
+    /// `--minimum-remaining-read-size`.
+    pub reads_dropped_too_short: u64,
+    pub reads_split: u64,
+    pub bases_trimmed_by_trimmer: HashMap<String, u64>,
+}
+
+impl Report {
+    pub fn new() -> Report {
+        Report::default()
+    }
+
+    pub fn record_read_in(&mut self) {
+        self.reads_in += 1;
+    }
+
+    /// Record that an input read (or read pair) was kept or dropped for being too short,
+    /// after surviving every trimmer. Call this at most once per input read/pair.
+    pub fn record_outcome(&mut self, kept: bool) {
+        if kept {
+            self.reads_kept += 1;
+        } else {
+            self.reads_dropped_too_short += 1;
+        }
+    }
+
+    /// Record that an input read (or read pair) was rejected outright by a trimmer,
+    /// before the minimum-length check ever ran.
+    pub fn record_rejected_by_trimmer(&mut self) {
+        self.reads_rejected_by_trimmer += 1;
+    }
+
+    /// Record how many bases a single named trimmer removed from one read (or, for
+    /// paired-end input, one mate of a read pair).
+    pub fn record_bases_trimmed(&mut self, trimmer_name: &str, bases_trimmed: usize) {
+        *self.bases_trimmed_by_trimmer.entry(trimmer_name.to_string()).or_insert(0) += bases_trimmed as u64;
+    }
+
+    /// Record that an input read (or read pair) came out of trimming as more than one
+    /// segment. Call this at most once per input read, regardless of how many trimmers
+    /// or mates contributed to the split.
+    pub fn record_split(&mut self) {
+        self.reads_split += 1;
+    }
+
+    /// Write this report as pretty-printed JSON to `path`.
+    pub fn write_to(&self, path: &str) -> io::Result<()> {
+        let file = File::create(path)?;
+        serde_json::to_writer_pretty(file, self).map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+    }
+}